@@ -17,3 +17,5 @@
 //@
 //@ [Error Handling](./errors.md)
 //@ [The Code Map](./codemap.md)
+//@ [Diagnostics](./diagnostics.md)
+//@ [Suggestions](./suggestions.md)
@@ -8,25 +8,114 @@
 //! A mapping from arbitrary locations and sections of source code to their
 //! contents.
 
-use std::collections::HashMap;
+use std::fmt;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::cmp;
 use std::cell::RefCell;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use lex::{Token, TokenKind};
 
-//@ We start off with a `Span`. This is really just a wrapper around an integer,
-//@ with the assumption that a span will **always** correspond to something in
-//@ the `CodeMap`. This means using a span from one `CodeMap` with another will
-//@ result in a panic if you are lucky, or silently give you garbage.
+//@ Not every `FileMap` corresponds to a real file on disk. Sometimes the source
+//@ comes from stdin, a REPL snippet, or is generated on the fly for a test, and
+//@ it's useful to be able to tell those apart from a genuine path -- both so we
+//@ know whether it's safe to re-read the file from disk, and so diagnostics can
+//@ show something sensible instead of a meaningless absolute path.
+
+/// Where a `FileMap`'s contents originally came from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FileName {
+    /// A file that exists on disk, at the given path.
+    Real(PathBuf),
+    /// Source code that doesn't correspond to a real file (e.g. stdin, a
+    /// REPL snippet, or generated code), identified by a logical name.
+    Virtual(String),
+}
+
+impl FileName {
+    /// Does this correspond to a real, on-disk file?
+    pub fn is_real(&self) -> bool {
+        match *self {
+            FileName::Real(_) => true,
+            FileName::Virtual(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileName::Real(ref path) => write!(f, "{}", path.display()),
+            FileName::Virtual(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl From<PathBuf> for FileName {
+    fn from(path: PathBuf) -> FileName {
+        FileName::Real(path)
+    }
+}
+
+impl<'a> From<&'a Path> for FileName {
+    fn from(path: &'a Path) -> FileName {
+        FileName::Real(path.to_path_buf())
+    }
+}
+
+impl From<String> for FileName {
+    fn from(name: String) -> FileName {
+        FileName::Virtual(name)
+    }
+}
+
+impl<'a> From<&'a str> for FileName {
+    fn from(name: &'a str) -> FileName {
+        FileName::Virtual(name.to_string())
+    }
+}
+
+//@ We start off with a `Span`. Originally this was just an opaque ID into a
+//@ per-file `HashMap<Span, Range<usize>>`, but that means every `insert_span()`
+//@ call does an `O(n)` scan (to dedupe identical ranges) and every `FileMap`
+//@ drags around a `RefCell` just to grow that map. Seeing as a `Range<usize>`
+//@ is only ever a handful of bytes, we can do much better by packing the file,
+//@ start, and length directly into the `Span` itself, turning lookups into
+//@ pure arithmetic.
+//@
+//@ The 64 bits of a `Span` are laid out as:
+//@
+//@ ```text
+//@ | escape (1) | file (15) | lo (32) | len (16) |
+//@ ```
+//@
+//@ `file` is a 1-based index into the `CodeMap`'s list of files (`0` is
+//@ reserved so `Span::dummy()` can never collide with a real span), `lo` is the
+//@ byte offset the span starts at, and `len` is how many bytes it covers. Most
+//@ spans are tiny, so 16 bits is normally plenty -- but on the off chance a
+//@ span is longer than `u16::MAX` bytes, we set the `escape` bit and stuff an
+//@ index into the owning `FileMap`'s overflow table into the remaining 48 bits
+//@ instead.
+
+const ESCAPE_BIT: u64 = 1 << 63;
+const FILE_BITS: u32 = 15;
+const FILE_SHIFT: u32 = 48;
+const FILE_MASK: u64 = (1 << FILE_BITS) - 1;
+const LO_BITS: u32 = 32;
+const LO_SHIFT: u32 = 16;
+const LO_MASK: u64 = (1 << LO_BITS) - 1;
+const LEN_MASK: u64 = (1 << 16) - 1;
+
+/// The largest byte length which can be packed directly into a `Span`. Ranges
+/// longer than this fall back to the owning `FileMap`'s overflow table.
+const MAX_INLINE_LEN: usize = LEN_MASK as usize;
 
 /// A unique identifier pointing to a substring in some file.
 ///
 /// To get back the original string this points to you'll need to look it up
-/// in a `CodeMap` or `FileMap`. 
+/// in a `CodeMap` or `FileMap`.
 #[derive(Copy, Clone, Debug, PartialEq, Hash, Eq, Serialize, Deserialize)]
-pub struct Span(usize);
+pub struct Span(u64);
 
 impl Span {
     /// Returns the special "dummy" span, which matches anything. This should
@@ -34,6 +123,44 @@ impl Span {
     pub(crate) fn dummy() -> Span {
         Span(0)
     }
+
+    fn inline(file: u16, lo: u32, len: u16) -> Span {
+        let file = u64::from(file) & FILE_MASK;
+        let lo = u64::from(lo) & LO_MASK;
+        let len = u64::from(len) & LEN_MASK;
+
+        Span((file << FILE_SHIFT) | (lo << LO_SHIFT) | len)
+    }
+
+    fn escape(file: u16, overflow_index: usize) -> Span {
+        let file = u64::from(file) & FILE_MASK;
+        let overflow_index = overflow_index as u64;
+
+        Span(ESCAPE_BIT | (file << FILE_SHIFT) | overflow_index)
+    }
+
+    fn file_index(self) -> u16 {
+        ((self.0 >> FILE_SHIFT) & FILE_MASK) as u16
+    }
+
+    fn is_escape(self) -> bool {
+        self.0 & ESCAPE_BIT != 0
+    }
+
+    fn overflow_index(self) -> usize {
+        debug_assert!(self.is_escape());
+        (self.0 & ((1 << FILE_SHIFT) - 1)) as usize
+    }
+
+    fn lo(self) -> u32 {
+        debug_assert!(!self.is_escape());
+        ((self.0 >> LO_SHIFT) & LO_MASK) as u32
+    }
+
+    fn len(self) -> u16 {
+        debug_assert!(!self.is_escape());
+        (self.0 & LEN_MASK) as u16
+    }
 }
 
 //@ For our purposes, the `CodeMap` will just contain a list of `FileMap`s. These
@@ -43,18 +170,22 @@ impl Span {
 /// A mapping of `Span`s to the files in which they are located.
 #[derive(Debug)]
 pub struct CodeMap {
-    next_id: Rc<AtomicUsize>,
     files: Vec<Rc<FileMap>>,
+    remappings: Vec<(PathBuf, PathBuf)>,
 }
 
 /// A mapping which keeps track of a file's contents and allows you to cheaply
 /// access substrings of the original content.
 #[derive(Clone, Debug)]
 pub struct FileMap {
-    name: String,
+    name: FileName,
     contents: String,
-    next_id: Rc<AtomicUsize>,
-    items: RefCell<HashMap<Span, Range<usize>>>
+    file_index: u16,
+    /// Ranges too long to pack inline into a `Span`. This should almost always
+    /// be empty.
+    overflow: RefCell<Vec<Range<usize>>>,
+    line_starts: Vec<usize>,
+    multi_byte_chars: Vec<usize>,
 }
 
 //@ The codemap has a couple useful methods for adding new files and looking up the
@@ -63,21 +194,28 @@ pub struct FileMap {
 impl CodeMap {
     /// Create a new, empty `CodeMap`.
     pub fn new() -> CodeMap {
-        let next_id = Rc::new(AtomicUsize::new(1));
-        let files = Vec::new();
-        CodeMap { next_id, files }
+        CodeMap { files: Vec::new(), remappings: Vec::new() }
     }
 
     /// Add a new file to the `CodeMap` and get back a reference to it.
-    pub fn insert_file<C, F>(&mut self, filename: F, contents: C) -> Rc<FileMap> 
-    where F: Into<String>,
+    pub fn insert_file<C, F>(&mut self, filename: F, contents: C) -> Rc<FileMap>
+    where F: Into<FileName>,
           C: Into<String>,
     {
+        let contents = contents.into();
+        let (line_starts, multi_byte_chars) = analyze_source(&contents);
+
+        debug_assert!(self.files.len() < FILE_MASK as usize,
+            "Ran out of file IDs to hand out");
+        let file_index = (self.files.len() + 1) as u16;
+
         let filemap = FileMap {
             name: filename.into(),
-            contents: contents.into(),
-            items: RefCell::new(HashMap::new()),
-            next_id: Rc::clone(&self.next_id),
+            contents,
+            file_index,
+            overflow: RefCell::new(Vec::new()),
+            line_starts,
+            multi_byte_chars,
         };
         let fm = Rc::new(filemap);
         self.files.push(Rc::clone(&fm));
@@ -87,20 +225,63 @@ impl CodeMap {
 
     /// Get the substring that this `Span` corresponds to.
     pub fn lookup(&self, span: Span) -> &str {
-        for filemap in &self.files {
-            if let Some(substr) = filemap.lookup(span) {
-                return substr;
-            }
-        }
-
-        panic!("Tried to lookup {:?} but it wasn't in any \
-            of the FileMaps... This is a bug!", span)
+        self.find_file(span)
+            .and_then(|filemap| filemap.lookup(span))
+            .unwrap_or_else(|| panic!("Tried to lookup {:?} but it wasn't in any \
+                of the FileMaps... This is a bug!", span))
     }
 
     /// The files that this `CodeMap` contains.
     pub fn files(&self) -> &[Rc<FileMap>] {
         self.files.as_slice()
     }
+
+    /// Find the `FileMap` which a `Span` was originally created from.
+    pub fn find_file(&self, span: Span) -> Option<&Rc<FileMap>> {
+        if span.file_index() == 0 {
+            return None;
+        }
+
+        self.files.get(span.file_index() as usize - 1)
+    }
+}
+
+//@ Analysis is often run somewhere other than the machine a report ends up
+//@ being read on (think CI), so absolute paths baked into a diagnostic aren't
+//@ very useful to the person reading it. `remap_path_prefix()` lets the
+//@ `CodeMap` rewrite those paths before they're displayed, without touching
+//@ the `FileName`s actually stored on each `FileMap`.
+
+impl CodeMap {
+    /// Register a rule so any `Real` path starting with `from` has that
+    /// prefix replaced by `to` whenever it's shown to the user (e.g. to turn
+    /// an absolute build path into something relative and stable).
+    pub fn remap_path_prefix<F, T>(&mut self, from: F, to: T)
+    where F: Into<PathBuf>,
+          T: Into<PathBuf>,
+    {
+        self.remappings.push((from.into(), to.into()));
+    }
+
+    /// Get the name that should be shown to the user for this file, with any
+    /// `remap_path_prefix()` rules applied to `Real` paths and `Virtual`
+    /// files tagged with their logical name.
+    pub fn display_path(&self, filemap: &FileMap) -> String {
+        match *filemap.name() {
+            FileName::Real(ref path) => self.remap(path).display().to_string(),
+            FileName::Virtual(ref name) => format!("<{}>", name),
+        }
+    }
+
+    fn remap(&self, path: &Path) -> PathBuf {
+        for &(ref from, ref to) in &self.remappings {
+            if let Ok(rest) = path.strip_prefix(from) {
+                return to.join(rest);
+            }
+        }
+
+        path.to_path_buf()
+    }
 }
 
 impl Default for CodeMap {
@@ -109,18 +290,29 @@ impl Default for CodeMap {
     }
 }
 
-//@ You may have noticed that `FileMap` contains a `RefCell<HashMap<_>>`. This is 
-//@ because we want to pass around multiple pointers to a file mapping, yet still
-//@ be able to add new spans if we want to. It also contains a reference to the
-//@ parent `CodeMap`'s counter so when we insert new spans into the `FileMap` 
-//@ they'll still get globally unique IDs.
+//@ Each `FileMap` knows its own (1-based) index, so it can tell at a glance
+//@ whether a `Span` even belongs to it. The `overflow` table only ever gets
+//@ touched for the rare span whose length doesn't fit in the inline bits.
 
 impl FileMap {
-    /// Get the name of this `FileMap`.
-    pub fn filename(&self) -> &str {
+    /// Get the name of this `FileMap`, formatted for display.
+    ///
+    /// This doesn't apply any of the `CodeMap`-level path remapping rules;
+    /// use `CodeMap::display_path()` if you want those taken into account.
+    pub fn filename(&self) -> String {
+        self.name.to_string()
+    }
+
+    /// Get this `FileMap`'s underlying `FileName`.
+    pub fn name(&self) -> &FileName {
         &self.name
     }
 
+    /// Does this `FileMap`'s content come from a real, on-disk file?
+    pub fn is_real(&self) -> bool {
+        self.name.is_real()
+    }
+
     /// Get the entire content of this file.
     pub fn contents(&self) -> &str {
         &self.contents
@@ -130,7 +322,7 @@ impl FileMap {
     ///
     /// # Panics
     ///
-    /// If the `FileMap`'s `items` hashmap contains a span, but that span 
+    /// If the `FileMap` thinks it owns this span, but the range it decodes to
     /// **doesn't** point to a valid substring this will panic. If you ever
     /// get into a situation like this then things are almost certainly FUBAR.
     pub fn lookup(&self, span: Span) -> Option<&str> {
@@ -148,15 +340,26 @@ impl FileMap {
 
     /// Get the range corresponding to this span.
     pub fn range_of(&self, span: Span) -> Option<Range<usize>> {
-        self.items.borrow().get(&span).cloned() 
+        if span.file_index() != self.file_index {
+            return None;
+        }
+
+        if span.is_escape() {
+            self.overflow.borrow().get(span.overflow_index()).cloned()
+        } else {
+            let start = span.lo() as usize;
+            let end = start + span.len() as usize;
+            Some(start..end)
+        }
     }
 }
 
-//@ Users can freely add new spans to a `FileMap`, to do this we'll take in the 
-//@ start and end indices, create a new span ID by incrementing our counter, then
-//@ we insert the new span and range into the `items`. In debug builds we'll do 
-//@ bounds checks, but it's an assumption that the `start` and `end` indices are
-//@ both within bounds, and lie on valid codepoint boundaries.
+//@ Users can freely add new spans to a `FileMap`. Encoding a span is now pure
+//@ arithmetic (no lookups required), so the only time we touch the `overflow`
+//@ table is for the rare range which is too long to pack inline. In debug
+//@ builds we'll do bounds checks, but it's an assumption that the `start` and
+//@ `end` indices are both within bounds, and lie on valid codepoint
+//@ boundaries.
 
 impl FileMap {
     /// Ask the `FileMap` to give you the span corresponding to the half-open
@@ -170,37 +373,25 @@ impl FileMap {
     /// It is assumed that the `start` and `indices` were originally obtained
     /// from the file's contents.
     pub fn insert_span(&self, start: usize, end: usize) -> Span {
-        debug_assert!(self.contents.is_char_boundary(start), 
+        debug_assert!(self.contents.is_char_boundary(start),
             "Start doesn't lie on a char boundary");
-        debug_assert!(self.contents.is_char_boundary(end), 
+        debug_assert!(self.contents.is_char_boundary(end),
             "End doesn't lie on a char boundary");
-        debug_assert!(start < self.contents.len(), 
+        debug_assert!(start < self.contents.len(),
             "Start lies outside the content string");
-        debug_assert!(end <= self.contents.len(), 
+        debug_assert!(end <= self.contents.len(),
             "End lies outside the content string");
 
-        let range = start..end;
+        let len = end - start;
 
-        if let Some(existing) = self.reverse_lookup(&range) {
-            return existing;
+        if start <= u32::max_value() as usize && len <= MAX_INLINE_LEN {
+            Span::inline(self.file_index, start as u32, len as u16)
+        } else {
+            let mut overflow = self.overflow.borrow_mut();
+            let index = overflow.len();
+            overflow.push(start..end);
+            Span::escape(self.file_index, index)
         }
-
-        let span_id = self.next_id.fetch_add(1, Ordering::Relaxed);
-        let span = Span(span_id);
-
-        self.items.borrow_mut().insert(span, range);
-        span
-    }
-
-    /// We don't want to go and add duplicate spans unnecessarily so we 
-    /// iterate through all existing ranges to see if this one already
-    /// exists. 
-    fn reverse_lookup(&self, needle: &Range<usize>) -> Option<Span> {
-        self.items.borrow()
-            .iter()
-            .find(|&(_, range)| range == needle)
-            .map(|(span, _)| span)
-            .cloned()
     }
 
     /// Merge two spans to get the span which includes both.
@@ -239,6 +430,84 @@ impl FileMap {
     }
 }
 
+//@ So far a `Span` can only tell you which bytes of a file it covers, which isn't
+//@ very helpful when you want to tell a human "hey, there's a problem on line 12".
+//@ To bridge that gap we pre-compute a *line table* the moment a file is added to
+//@ the `CodeMap` (scanning the source once, rather than on every lookup), and use
+//@ it to resolve a byte offset into a `LineColumn`.
+
+/// A `(line, column)` pair, both zero-based, pointing at a particular character
+/// in a `FileMap`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineColumn {
+    /// The (zero-based) line number.
+    pub line: usize,
+    /// The (zero-based) column, counted in characters rather than bytes.
+    pub column: usize,
+}
+
+//@ Building the line table itself is just a single pass over the source, noting
+//@ the byte offset of the start of each line (the file always starts a line at
+//@ offset `0`) as well as the offset of every multi-byte UTF-8 character. We keep
+//@ the latter around so columns can later be reported in characters instead of
+//@ bytes, without having to re-scan the string every time.
+
+fn analyze_source(src: &str) -> (Vec<usize>, Vec<usize>) {
+    let mut line_starts = vec![0];
+    let mut multi_byte_chars = Vec::new();
+
+    for (index, ch) in src.char_indices() {
+        if ch == '\n' {
+            line_starts.push(index + 1);
+        }
+
+        if ch.len_utf8() > 1 {
+            multi_byte_chars.push(index);
+        }
+    }
+
+    (line_starts, multi_byte_chars)
+}
+
+impl FileMap {
+    /// Look up the `(start, end)` `LineColumn`s that a `Span` covers.
+    ///
+    /// The end position is exclusive (just like the underlying byte range), so
+    /// it may point one character past the end of the file's contents.
+    pub fn lookup_pos(&self, span: Span) -> (LineColumn, LineColumn) {
+        let range = self.range_of(span).expect("Span doesn't belong to this FileMap");
+
+        let end = cmp::min(range.end, self.contents.len());
+        (self.lookup_offset(range.start), self.lookup_offset(end))
+    }
+
+    fn lookup_offset(&self, offset: usize) -> LineColumn {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+
+        let first = match self.multi_byte_chars.binary_search(&line_start) {
+            Ok(i) | Err(i) => i,
+        };
+        let extra_bytes: usize = self.multi_byte_chars[first..]
+            .iter()
+            .take_while(|&&idx| idx < offset)
+            .map(|&idx| {
+                let ch = self.contents[idx..].chars().next().expect("char start must be valid");
+                ch.len_utf8() - 1
+            })
+            .sum();
+
+        // every multi-byte char takes up more bytes than it does columns, so
+        // each one we've seen inflates the naive byte-based column count
+        let column = (offset - line_start) - extra_bytes;
+
+        LineColumn { line, column }
+    }
+}
+
 //@ To test that our `CodeMap` and `FileMap` behave as we expect them to, let's
 //@ create some dummy "files" and try to create spans in them.
 
@@ -330,4 +599,105 @@ mod tests {
         assert_eq!(equivalent_range.start, 0);
         assert_eq!(equivalent_range.end, 8);
     }
+
+    #[test]
+    fn lookup_line_and_column_on_first_line() {
+        let mut map = CodeMap::new();
+        let src = "Hello World!";
+        let fm = map.insert_file("foo.rs", src);
+
+        let span = fm.insert_span(6, 11);
+        let (start, end) = fm.lookup_pos(span);
+
+        assert_eq!(start, LineColumn { line: 0, column: 6 });
+        assert_eq!(end, LineColumn { line: 0, column: 11 });
+    }
+
+    #[test]
+    fn lookup_line_and_column_after_a_newline() {
+        let mut map = CodeMap::new();
+        let src = "Hello\nWorld!";
+        let fm = map.insert_file("foo.rs", src);
+
+        let span = fm.insert_span(6, 11);
+        let (start, end) = fm.lookup_pos(span);
+
+        assert_eq!(start, LineColumn { line: 1, column: 0 });
+        assert_eq!(end, LineColumn { line: 1, column: 5 });
+    }
+
+    #[test]
+    fn lookup_line_and_column_counts_multi_byte_chars() {
+        let mut map = CodeMap::new();
+        let src = "héllo world";
+        let fm = map.insert_file("foo.rs", src);
+
+        // "world" starts after the 'é', which takes up 2 bytes but 1 column
+        let world_starts = src.find("world").unwrap();
+        let span = fm.insert_span(world_starts, src.len());
+        let (start, _) = fm.lookup_pos(span);
+
+        assert_eq!(start, LineColumn { line: 0, column: 6 });
+    }
+
+    #[test]
+    fn spans_from_different_files_dont_collide() {
+        let mut map = CodeMap::new();
+        let first = map.insert_file("a.rs", "Hello World!");
+        let second = map.insert_file("b.rs", "Hello World!");
+
+        let span_a = first.insert_span(0, 5);
+        let span_b = second.insert_span(0, 5);
+
+        assert_eq!(first.range_of(span_b), None);
+        assert_eq!(second.range_of(span_a), None);
+    }
+
+    #[test]
+    fn spans_too_long_to_pack_inline_use_the_overflow_table() {
+        let mut map = CodeMap::new();
+        let src: String = "x".repeat(MAX_INLINE_LEN + 1);
+        let fm = map.insert_file("foo.rs", src.clone());
+
+        let span = fm.insert_span(0, src.len());
+
+        assert_eq!(fm.range_of(span), Some(0..src.len()));
+        assert_eq!(fm.lookup(span).unwrap(), src.as_str());
+    }
+
+    #[test]
+    fn plain_strings_are_virtual_files() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file("snippet", "var x;");
+
+        assert!(!fm.is_real());
+        assert_eq!(fm.filename(), "snippet");
+    }
+
+    #[test]
+    fn paths_are_real_files() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file(PathBuf::from("/tmp/build/foo.pas"), "var x;");
+
+        assert!(fm.is_real());
+    }
+
+    #[test]
+    fn virtual_files_are_tagged_when_displayed() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file("snippet", "var x;");
+
+        assert_eq!(map.display_path(&fm), "<snippet>");
+    }
+
+    #[test]
+    fn remap_path_prefix_rewrites_display_paths() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file(PathBuf::from("/tmp/build/src/foo.pas"), "var x;");
+
+        map.remap_path_prefix("/tmp/build", ".");
+
+        let should_be = PathBuf::from(".").join("src/foo.pas").display().to_string();
+        assert_eq!(map.display_path(&fm), should_be);
+    }
 }
@@ -0,0 +1,209 @@
+//@ # Diagnostics
+//@
+//@ The `errors` module gives us a way to represent *internal* failures (things
+//@ going wrong while we're trying to do our job), but it's not very good at
+//@ showing a *user* where the problem in their code is. For that we want
+//@ something closer to what `rustc` gives you; a message, the offending line(s)
+//@ of source code, and a caret pointing at the exact columns which are broken.
+
+//! Rich, `rustc`-style diagnostic reporting.
+
+use std::fmt::Write;
+use codemap::{CodeMap, LineColumn, Span};
+
+//@ First up is the `Level` of a `Diagnostic`. This lets the user (and the
+//@ `Emitter`) know just how seriously they should take a particular message.
+
+/// How severe a `Diagnostic` is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Level {
+    /// Something is definitely wrong and analysis can't continue.
+    Error,
+    /// Something looks wrong, but it's not necessarily fatal.
+    Warning,
+    /// Extra context about a `Warning` or `Error`.
+    Note,
+    /// A suggestion for how the user might fix things.
+    Help,
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+            Level::Help => "help",
+        }
+    }
+}
+
+//@ A `Diagnostic` is built around one primary `Span` (the thing the message is
+//@ really about), with any number of secondary spans (e.g. "previously defined
+//@ here") and an optional trailing help note.
+
+/// A `Span` annotated with a short message, used to add extra context to a
+/// `Diagnostic`.
+#[derive(Clone, Debug)]
+struct Label {
+    span: Span,
+    message: String,
+}
+
+/// A single diagnostic report which can be handed to an `Emitter`.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    level: Level,
+    message: String,
+    primary_span: Span,
+    secondary_spans: Vec<Label>,
+    help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a new `Diagnostic`, pointing at the `Span` which triggered it.
+    pub fn new<S: Into<String>>(level: Level, message: S, span: Span) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.into(),
+            primary_span: span,
+            secondary_spans: Vec::new(),
+            help: None,
+        }
+    }
+
+    /// Annotate an additional `Span` with a message (e.g. "previously defined
+    /// here").
+    pub fn with_secondary_span<S: Into<String>>(mut self, span: Span, message: S) -> Diagnostic {
+        self.secondary_spans.push(Label { span, message: message.into() });
+        self
+    }
+
+    /// Attach a trailing help message, suggesting how the user might fix things.
+    pub fn with_help<S: Into<String>>(mut self, message: S) -> Diagnostic {
+        self.help = Some(message.into());
+        self
+    }
+}
+
+//@ With the `Diagnostic` itself out of the way, the interesting part is the
+//@ `Emitter`. Given a `CodeMap` to resolve spans against, it turns a
+//@ `Diagnostic` into the kind of report you'd see from a real compiler.
+
+/// Renders `Diagnostic`s using the files registered in a `CodeMap`.
+#[derive(Debug)]
+pub struct Emitter<'a> {
+    codemap: &'a CodeMap,
+}
+
+impl<'a> Emitter<'a> {
+    /// Create a new `Emitter` which resolves spans using the given `CodeMap`.
+    pub fn new(codemap: &'a CodeMap) -> Emitter<'a> {
+        Emitter { codemap }
+    }
+
+    /// Render a `Diagnostic` and print it to stderr.
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        eprintln!("{}", self.render(diagnostic));
+    }
+
+    /// Render a `Diagnostic` to a `String` instead of printing it, mainly
+    /// useful for testing.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut buffer = String::new();
+
+        self.write_annotation(&mut buffer, diagnostic.level, &diagnostic.message,
+            diagnostic.primary_span, '^');
+
+        for label in &diagnostic.secondary_spans {
+            buffer.push('\n');
+            self.write_annotation(&mut buffer, Level::Note, &label.message, label.span, '-');
+        }
+
+        if let Some(ref help) = diagnostic.help {
+            buffer.push('\n');
+            write!(buffer, "{}: {}", Level::Help.as_str(), help).expect("writing to a String can't fail");
+        }
+
+        buffer
+    }
+
+    //@ Rendering a single annotation means finding which file the span belongs
+    //@ to, resolving its `LineColumn`s, then printing the line of source it
+    //@ starts on with an underline beneath the highlighted columns. Only that
+    //@ first line is printed; for a span running over several lines, the
+    //@ underline runs to the end of the first line and the later lines are
+    //@ not shown.
+
+    fn write_annotation(&self, buffer: &mut String, level: Level, message: &str, span: Span, underline: char) {
+        match self.codemap.find_file(span) {
+            Some(filemap) => {
+                let (start, end) = filemap.lookup_pos(span);
+
+                writeln!(buffer, "{}:{}:{}: {}: {}",
+                    self.codemap.display_path(filemap), start.line + 1, start.column + 1, level.as_str(), message)
+                    .expect("writing to a String can't fail");
+
+                let line = filemap.contents().lines().nth(start.line).unwrap_or("");
+                writeln!(buffer, "{}", line).expect("writing to a String can't fail");
+
+                let end_column = if end.line == start.line {
+                    end.column
+                } else {
+                    line.chars().count()
+                };
+                let width = if end_column > start.column { end_column - start.column } else { 1 };
+
+                let indent: String = " ".repeat(start.column);
+                let marks: String = underline.to_string().repeat(width);
+                write!(buffer, "{}{}", indent, marks).expect("writing to a String can't fail");
+            }
+            None => {
+                write!(buffer, "{}: {}", level.as_str(), message).expect("writing to a String can't fail");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use codemap::CodeMap;
+
+    #[test]
+    fn emit_a_simple_error() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file(PathBuf::from("foo.pas"), "x := 1 + ;");
+
+        let span = fm.insert_span(9, 10);
+        let diag = Diagnostic::new(Level::Error, "expected an expression", span);
+
+        let emitter = Emitter::new(&map);
+        let rendered = emitter.render(&diag);
+
+        assert!(rendered.contains("foo.pas:1:10: error: expected an expression"));
+        assert!(rendered.contains("x := 1 + ;"));
+        assert!(rendered.contains('^'));
+    }
+
+    #[test]
+    fn emit_with_secondary_span_and_help() {
+        let mut map = CodeMap::new();
+        let fm = map.insert_file("foo.pas", "var x: Integer;\nvar x: Integer;");
+
+        let first = fm.insert_span(4, 5);
+        let second = fm.insert_span(20, 21);
+
+        let diag = Diagnostic::new(Level::Error, "`x` is defined multiple times", second)
+            .with_secondary_span(first, "previously defined here")
+            .with_help("rename one of these variables");
+
+        let emitter = Emitter::new(&map);
+        let rendered = emitter.render(&diag);
+
+        assert!(rendered.contains("error: `x` is defined multiple times"));
+        assert!(rendered.contains("note: previously defined here"));
+        assert!(rendered.contains("help: rename one of these variables"));
+    }
+}
@@ -3,18 +3,19 @@
 
 
 use codemap::{CodeMap, FileMap, Span};
+use diagnostics::{Diagnostic, Emitter};
 
-//@ The `Driver` contains a `CodeMap` and various other configuration settings 
+//@ The `Driver` contains a `CodeMap` and various other configuration settings
 //@ required to run the analysis.
 
-/// The driver is in charge of orchestrating the whole analysis process and 
+/// The driver is in charge of orchestrating the whole analysis process and
 /// making sure all the bits and pieces integrate nicely.
 #[derive(Debug)]
 pub struct Driver {
     codemap: CodeMap,
 }
 
-//@ He has various methods to allow users to add files to be analysed, as well as 
+//@ He has various methods to allow users to add files to be analysed, as well as
 //@ other convenience methods for setting things up.
 
 impl Driver {
@@ -29,6 +30,13 @@ impl Driver {
     pub fn codemap(&mut self) -> &mut CodeMap {
         &mut self.codemap
     }
+
+    /// Report a `Diagnostic` to the user, using the `Driver`'s `CodeMap` to
+    /// resolve spans into the source snippets they point at.
+    pub fn emit(&self, diagnostic: &Diagnostic) {
+        let emitter = Emitter::new(&self.codemap);
+        emitter.emit(diagnostic);
+    }
 }
 
 impl Default for Driver {
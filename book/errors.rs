@@ -6,6 +6,8 @@
 
 //! Types and traits used for internal errors.
 
+use lex::Location;
+
 error_chain!{
     errors {
         /// Got to the end of the input stream but was expecting more.
@@ -21,7 +23,7 @@ error_chain!{
         }
 
         /// A message which corresponds to some location in the source code.
-        MessageWithLocation(loc: usize, msg: &'static str) {
+        MessageWithLocation(loc: Location, msg: &'static str) {
             display("{} at {}", msg, loc)
             description("Custom Error")
         }
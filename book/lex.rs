@@ -6,6 +6,8 @@
 
 //@ Before anything else, lets import some things we'll require.
 
+use std::borrow::Cow;
+use std::fmt;
 use std::str;
 use codemap::Span;
 use errors::*;
@@ -24,10 +26,12 @@ use errors::*;
 #[allow(missing_docs)]
 #[serde(tag = "type")]
 pub enum TokenKind {
-    Integer(usize),
-    Decimal(f64),
+    Number(NumberLiteral),
     Identifier(String),
+    Keyword(Keyword),
     QuotedString(String),
+    Unknown(char),
+    CompilerDirective { name: String, args: String },
     Asterisk,
     At, 
     Carat, 
@@ -61,16 +65,93 @@ impl<'a> From<&'a str> for TokenKind {
 
 impl From<usize> for TokenKind {
     fn from(other: usize) -> TokenKind {
-        TokenKind::Integer(other)
+        TokenKind::Number(NumberLiteral {
+            raw: other.to_string(),
+            value: Number::Integer(other),
+            kind: None,
+        })
     }
 }
 
 impl From<f64> for TokenKind {
     fn from(other: f64) -> TokenKind {
-        TokenKind::Decimal(other)
+        TokenKind::Number(NumberLiteral {
+            raw: other.to_string(),
+            value: Number::Decimal(other),
+            kind: None,
+        })
     }
 }
 
+//@ Previously a number was either an `Integer(usize)` or a `Decimal(f64)`,
+//@ but once we want to support alternate spellings like `$FF` or `1.5e10`
+//@ that's not quite enough -- callers may well want to know *how* a number
+//@ was written, not just what it evaluates to. So a `NumberLiteral` bundles
+//@ the parsed value together with the raw text it came from and, if it used
+//@ one of those alternate spellings, a tag saying which.
+
+/// A numeric literal, together with the raw source text it was parsed from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NumberLiteral {
+    /// The literal's parsed value.
+    pub value: Number,
+    /// The exact source text this literal was parsed from.
+    pub raw: String,
+    /// Which alternate syntax (if any) this literal used.
+    pub kind: Option<NumberKind>,
+}
+
+/// The value of a numeric literal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum Number {
+    Integer(usize),
+    Decimal(f64),
+}
+
+/// An alternate syntax used to write a numeric literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum NumberKind {
+    Hex,
+    Scientific,
+}
+
+//@ `TokenKind` owns every string it carries, which is the right default but
+//@ means tokenizing a large file allocates one `String` per identifier and
+//@ quoted string, even though most of the time the token's text is sitting
+//@ right there in the source we already have a reference to. `TokenKindRef`
+//@ is the same set of tokens, except identifiers borrow their text straight
+//@ out of the source and quoted strings use a `Cow` -- borrowed whenever the
+//@ literal has no escapes to decode, owned when it does.
+
+/// Equivalent to [`TokenKind`], except text is borrowed out of the original
+/// source wherever possible instead of being copied into a new `String`.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(missing_docs)]
+pub enum TokenKindRef<'a> {
+    Number(NumberLiteral),
+    Identifier(&'a str),
+    Keyword(Keyword),
+    QuotedString(Cow<'a, str>),
+    Unknown(char),
+    CompilerDirective { name: &'a str, args: &'a str },
+    Asterisk,
+    At,
+    Carat,
+    CloseParen,
+    CloseSquare,
+    Colon,
+    Dot,
+    End,
+    Equals,
+    Minus,
+    OpenParen,
+    OpenSquare,
+    Plus,
+    Semicolon,
+    Slash,
+}
 
 //@ ## Tokenizing Individual Atoms
 //@
@@ -87,12 +168,91 @@ fn tokenize_ident(data: &str) -> Result<(TokenKind, usize)> {
 
     let (got, bytes_read) = take_while(data, |ch| ch == '_' || ch.is_alphanumeric())?;
 
-    // TODO: Recognise keywords using a `match` statement here.
+    let tok = keyword(got).unwrap_or_else(|| TokenKind::Identifier(got.to_string()));
+    Ok((tok, bytes_read))
+}
+
+/// Like `tokenize_ident`, but borrows the identifier's text out of `data`
+/// instead of allocating a `String` for it.
+fn tokenize_ident_borrowed(data: &str) -> Result<(TokenKindRef, usize)> {
+    match data.chars().next() {
+        Some(ch) if ch.is_digit(10) => bail!("Identifiers can't start with a number"),
+        None => bail!(ErrorKind::UnexpectedEOF),
+        _ => {},
+    }
+
+    let (got, bytes_read) = take_while(data, |ch| ch == '_' || ch.is_alphanumeric())?;
 
-    let tok = TokenKind::Identifier(got.to_string());
+    let tok = match keyword(got) {
+        Some(TokenKind::Keyword(k)) => TokenKindRef::Keyword(k),
+        Some(TokenKind::End) => TokenKindRef::End,
+        Some(_) => unreachable!("keyword() only ever returns a Keyword or End variant"),
+        None => TokenKindRef::Identifier(got),
+    };
     Ok((tok, bytes_read))
 }
 
+//@ Delphi is case-insensitive, so `BEGIN`, `Begin`, and `begin` should all be
+//@ recognised as the same reserved word. Rather than bloat `TokenKind` with a
+//@ variant per keyword, we group them into their own `Keyword` enum and wrap
+//@ that in a single `TokenKind::Keyword`. The one exception is `end`, which
+//@ already had its own `TokenKind::End` variant before we got here -- there's
+//@ no reason to introduce a second way of spelling the same token.
+
+/// A reserved word in the Delphi language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(missing_docs)]
+pub enum Keyword {
+    Begin,
+    Procedure,
+    Function,
+    Unit,
+    Interface,
+    Implementation,
+    Uses,
+    Var,
+    Const,
+    Type,
+    If,
+    Then,
+    Else,
+    While,
+    Do,
+    For,
+    Repeat,
+    Until,
+    Case,
+}
+
+/// Check whether `word` is a Delphi reserved word, matching case-insensitively.
+fn keyword(word: &str) -> Option<TokenKind> {
+    let keyword = match word.to_lowercase().as_str() {
+        "begin" => Keyword::Begin,
+        "end" => return Some(TokenKind::End),
+        "procedure" => Keyword::Procedure,
+        "function" => Keyword::Function,
+        "unit" => Keyword::Unit,
+        "interface" => Keyword::Interface,
+        "implementation" => Keyword::Implementation,
+        "uses" => Keyword::Uses,
+        "var" => Keyword::Var,
+        "const" => Keyword::Const,
+        "type" => Keyword::Type,
+        "if" => Keyword::If,
+        "then" => Keyword::Then,
+        "else" => Keyword::Else,
+        "while" => Keyword::While,
+        "do" => Keyword::Do,
+        "for" => Keyword::For,
+        "repeat" => Keyword::Repeat,
+        "until" => Keyword::Until,
+        "case" => Keyword::Case,
+        _ => return None,
+    };
+
+    Some(TokenKind::Keyword(keyword))
+}
+
 //@ The `take_while()` function is just a helper which will call a closure on each
 //@ byte, continuing until the closure no longer returns `true`. 
 //@
@@ -163,6 +323,9 @@ lexer_test!(tokenize_an_identifer, tokenize_ident, "Foo" => "Foo");
 lexer_test!(tokenize_ident_containing_an_underscore, tokenize_ident, "Foo_bar" => "Foo_bar");
 lexer_test!(FAIL: tokenize_ident_cant_start_with_number, tokenize_ident, "7Foo_bar");
 lexer_test!(FAIL: tokenize_ident_cant_start_with_dot, tokenize_ident, ".Foo_bar");
+lexer_test!(tokenize_the_begin_keyword, tokenize_ident, "begin" => TokenKind::Keyword(Keyword::Begin));
+lexer_test!(tokenize_keywords_case_insensitively, tokenize_ident, "PROCEDURE" => TokenKind::Keyword(Keyword::Procedure));
+lexer_test!(tokenize_end_keeps_using_the_existing_variant, tokenize_ident, "End" => TokenKind::End);
 
 //@ Note that the macro calls `into()` on the result for us. Because we've defined
 //@ `From<&'a str>` for `TokenKind`, we can use `"Foo"` as shorthand for the output.
@@ -176,9 +339,13 @@ lexer_test!(FAIL: tokenize_ident_cant_start_with_dot, tokenize_ident, ".Foo_bar"
 
 /// Tokenize a numeric literal.
 fn tokenize_number(data: &str) -> Result<(TokenKind, usize)> {
+    if data.starts_with('$') {
+        return tokenize_hex_number(data);
+    }
+
     let mut seen_dot = false;
 
-    let (decimal, bytes_read) = take_while(data, |c| {
+    let (_, mut bytes_read) = take_while(data, |c| {
         if c.is_digit(10) {
             true
         } else if c == '.' {
@@ -193,17 +360,28 @@ fn tokenize_number(data: &str) -> Result<(TokenKind, usize)> {
         }
     })?;
 
-    if seen_dot {
-        let n: f64 = decimal.parse()?;
-        Ok((TokenKind::Decimal(n), bytes_read))
-    } else {
-        let n: usize = decimal.parse()?;
-        Ok((TokenKind::Integer(n), bytes_read))
+    let mut kind = None;
 
+    if data[bytes_read..].starts_with('e') || data[bytes_read..].starts_with('E') {
+        bytes_read += tokenize_exponent(&data[bytes_read..])?;
+        seen_dot = true;
+        kind = Some(NumberKind::Scientific);
     }
+
+    let raw = &data[..bytes_read];
+
+    let literal = if seen_dot {
+        let n: f64 = raw.parse()?;
+        NumberLiteral { value: Number::Decimal(n), raw: raw.to_string(), kind }
+    } else {
+        let n: usize = raw.parse()?;
+        NumberLiteral { value: Number::Integer(n), raw: raw.to_string(), kind }
+    };
+
+    Ok((TokenKind::Number(literal), bytes_read))
 }
 
-//@ Something interesting with this approach is that a literal like `12.4.789` 
+//@ Something interesting with this approach is that a literal like `12.4.789`
 //@ will be lexed as the decimal `12.4` followed by a `.789`, which is an invalid
 //@ float.
 
@@ -215,7 +393,229 @@ lexer_test!(tokenize_string_with_multiple_decimal_points, tokenize_number, "12.3
 lexer_test!(FAIL: cant_tokenize_a_string_as_a_decimal, tokenize_number, "asdfghj");
 lexer_test!(tokenizing_decimal_stops_at_alpha, tokenize_number, "123.4asdfghj" => 123.4);
 
-//@ One last utility we're going to need is the ability to skip past whitespace 
+//@ Delphi also lets you write integers in hexadecimal (`$FF`) and floats using
+//@ scientific notation (`1.5e10`, `2E-3`). Both are just a different spelling
+//@ for the same underlying value, so rather than inventing more `TokenKind`
+//@ variants we tag the existing `NumberLiteral` with which syntax was used.
+
+/// Tokenize a `$FF`-style hexadecimal integer literal.
+fn tokenize_hex_number(data: &str) -> Result<(TokenKind, usize)> {
+    debug_assert!(data.starts_with('$'));
+
+    let (digits, digits_read) = take_while(&data[1..], |c| c.is_ascii_hexdigit())
+        .chain_err(|| "Expected at least one hex digit after '$'")?;
+
+    let n = usize::from_str_radix(digits, 16)?;
+    let bytes_read = 1 + digits_read;
+    let raw = &data[..bytes_read];
+
+    let literal = NumberLiteral {
+        value: Number::Integer(n),
+        raw: raw.to_string(),
+        kind: Some(NumberKind::Hex),
+    };
+    Ok((TokenKind::Number(literal), bytes_read))
+}
+
+/// Consume a `[eE][+-]?digits` exponent suffix, returning how many bytes it
+/// took up. Assumes `data` starts with `e` or `E`.
+fn tokenize_exponent(data: &str) -> Result<usize> {
+    debug_assert!(data.starts_with('e') || data.starts_with('E'));
+
+    let after_e = &data[1..];
+    let (sign_len, after_sign) = match after_e.chars().next() {
+        Some('+') | Some('-') => (1, &after_e[1..]),
+        _ => (0, after_e),
+    };
+
+    let (_, digits_read) = take_while(after_sign, |c| c.is_digit(10))
+        .chain_err(|| "Expected at least one digit in the exponent")?;
+
+    Ok(1 + sign_len + digits_read)
+}
+
+macro_rules! number_test {
+    (FAIL: $name:ident, $src:expr) => {
+        #[cfg(test)]
+        #[test]
+        fn $name() {
+            let got = tokenize_number($src);
+            assert!(got.is_err(), "{:?} should be an error", got);
+        }
+    };
+    ($name:ident, $src:expr => $value:expr, $kind:expr) => {
+        #[cfg(test)]
+        #[test]
+        fn $name() {
+            let src: &str = $src;
+            let should_be = NumberLiteral { value: $value, raw: src.to_string(), kind: $kind };
+
+            let (got, bytes_read) = tokenize_number(src).unwrap();
+            assert_eq!(got, TokenKind::Number(should_be));
+            assert_eq!(bytes_read, src.len());
+        }
+    };
+}
+
+number_test!(tokenize_a_hex_integer, "$FF" => Number::Integer(255), Some(NumberKind::Hex));
+number_test!(tokenize_a_longer_hex_integer, "$1A2B" => Number::Integer(0x1A2B), Some(NumberKind::Hex));
+number_test!(tokenize_scientific_notation, "1.5e3" => Number::Decimal(1500.0), Some(NumberKind::Scientific));
+number_test!(tokenize_scientific_notation_with_negative_exponent, "2E-3" => Number::Decimal(2E-3), Some(NumberKind::Scientific));
+number_test!(FAIL: bare_dollar_sign_is_not_a_valid_hex_number, "$ ");
+number_test!(FAIL: exponent_with_no_digits_is_invalid, "1.5e");
+
+//@ Delphi string literals are a little more fiddly than you'd expect. A string
+//@ is delimited by single quotes, with a doubled quote (`''`) inside one of
+//@ those runs representing a single literal quote character. On top of that,
+//@ Delphi lets you splice in raw character codes with `#13` (decimal) or
+//@ `#$0D` (hex), and these can be concatenated directly against quoted runs
+//@ with no operator in between, e.g. `'Hello'#13#10'World'`. We handle this by
+//@ repeatedly consuming either a quoted run or a `#code` piece until neither
+//@ pattern matches any more, appending each piece's decoded text as we go.
+
+/// Tokenize a Delphi string literal, which may be made up of several quoted
+/// and `#code` pieces concatenated directly next to each other.
+fn tokenize_string(data: &str) -> Result<(TokenKind, usize)> {
+    let mut decoded = String::new();
+    let mut remaining = data;
+    let mut bytes_read = 0;
+
+    loop {
+        if remaining.starts_with('\'') {
+            let (piece, consumed) = tokenize_quoted_run(remaining)?;
+            decoded.push_str(&piece);
+            remaining = &remaining[consumed..];
+            bytes_read += consumed;
+        } else if remaining.starts_with('#') {
+            let (ch, consumed) = tokenize_char_code(remaining)?;
+            decoded.push(ch);
+            remaining = &remaining[consumed..];
+            bytes_read += consumed;
+        } else {
+            break;
+        }
+    }
+
+    if bytes_read == 0 {
+        bail!(ErrorKind::UnexpectedEOF);
+    }
+
+    Ok((TokenKind::QuotedString(decoded), bytes_read))
+}
+
+/// Like `tokenize_string`, but avoids allocating when the literal is a
+/// single `'...'` run with no escaped quotes or `#code` pieces, borrowing its
+/// text out of `data` instead.
+fn tokenize_string_borrowed(data: &str) -> Result<(TokenKindRef, usize)> {
+    if let Some((borrowed, bytes_read)) = borrow_simple_quoted_run(data) {
+        return Ok((TokenKindRef::QuotedString(Cow::Borrowed(borrowed)), bytes_read));
+    }
+
+    let (tok, bytes_read) = tokenize_string(data)?;
+    match tok {
+        TokenKind::QuotedString(s) => Ok((TokenKindRef::QuotedString(Cow::Owned(s)), bytes_read)),
+        _ => unreachable!("tokenize_string only ever returns a QuotedString"),
+    }
+}
+
+/// Try to borrow a string literal's text directly out of `data`, which is
+/// only possible when it's a lone `'...'` run -- no doubled quotes to
+/// un-escape and no `#code` piece concatenated onto it.
+fn borrow_simple_quoted_run(data: &str) -> Option<(&str, usize)> {
+    if !data.starts_with('\'') {
+        return None;
+    }
+
+    let closing = data[1..].find('\'')?;
+    let end = 1 + closing;
+
+    if data[end + 1..].starts_with('\'') || data[end + 1..].starts_with('#') {
+        return None;
+    }
+
+    Some((&data[1..end], end + 1))
+}
+
+/// Tokenize a single `'...'` run, un-escaping any doubled quotes along the way.
+fn tokenize_quoted_run(data: &str) -> Result<(String, usize)> {
+    debug_assert!(data.starts_with('\''));
+
+    let mut decoded = String::new();
+    let mut chars = data.char_indices().skip(1);
+
+    loop {
+        match chars.next() {
+            Some((idx, '\'')) => {
+                match data[idx + 1..].chars().next() {
+                    // a doubled quote is an escaped literal quote character
+                    Some('\'') => {
+                        decoded.push('\'');
+                        chars.next();
+                    }
+                    // otherwise we've found the end of this run
+                    _ => return Ok((decoded, idx + 1)),
+                }
+            }
+            Some((_, ch)) => decoded.push(ch),
+            None => bail!(ErrorKind::UnexpectedEOF),
+        }
+    }
+}
+
+/// Tokenize a `#13` or `#$0D` character code, returning the character it
+/// represents.
+fn tokenize_char_code(data: &str) -> Result<(char, usize)> {
+    debug_assert!(data.starts_with('#'));
+
+    let rest = &data[1..];
+
+    let (code, digits_read) = if rest.starts_with('$') {
+        let (digits, n) = take_while(&rest[1..], |c| c.is_ascii_hexdigit())
+            .chain_err(|| "Expected at least one hex digit after '#$'")?;
+        (u32::from_str_radix(digits, 16)?, n + 1)
+    } else {
+        let (digits, n) = take_while(rest, |c| c.is_digit(10))
+            .chain_err(|| "Expected at least one digit after '#'")?;
+        (digits.parse()?, n)
+    };
+
+    match char::from_u32(code) {
+        Some(ch) => Ok((ch, digits_read + 1)),
+        None => bail!("{} is not a valid character code", code),
+    }
+}
+
+macro_rules! string_test {
+    (FAIL: $name:ident, $src:expr) => {
+        #[cfg(test)]
+        #[test]
+        fn $name() {
+            let got = tokenize_string($src);
+            assert!(got.is_err(), "{:?} should be an error", got);
+        }
+    };
+    ($name:ident, $src:expr => $should_be:expr) => {
+        #[cfg(test)]
+        #[test]
+        fn $name() {
+            let src: &str = $src;
+            let should_be = TokenKind::QuotedString($should_be.to_string());
+
+            let (got, _bytes_read) = tokenize_string(src).unwrap();
+            assert_eq!(got, should_be, "Input was {:?}", src);
+        }
+    };
+}
+
+string_test!(tokenize_an_empty_string, "''" => "");
+string_test!(tokenize_a_simple_string, "'Hello World'" => "Hello World");
+string_test!(tokenize_a_string_with_an_escaped_quote, "'it''s'" => "it's");
+string_test!(tokenize_a_decimal_char_code, "#65" => "A");
+string_test!(tokenize_a_hex_char_code, "#$41" => "A");
+string_test!(tokenize_mixed_quotes_and_char_codes, "'Hello'#13#10'World'" => "Hello\r\nWorld");
+string_test!(FAIL: tokenize_unterminated_string, "'Hello");
+
+//@ One last utility we're going to need is the ability to skip past whitespace
 //@ characters and comments. These will be implemented as two separate functions
 //@ which are wrapped by a single `skip()`.
 //@
@@ -246,8 +646,6 @@ fn skipping_whitespace_when_first_is_a_letter_returns_zero() {
     assert_eq!(num_skipped, should_be);
 }
 
-//@ > **TODO:** Tokenize string literals
-//@
 //@ According to [the internets], a comment in Delphi can be written multiple ways.
 //@
 //@ > **Commenting Code**
@@ -269,6 +667,12 @@ fn skip_comments(src: &str) -> usize {
 
     for &(pattern, matcher) in &pairs {
         if src.starts_with(pattern) {
+            if is_compiler_directive(src, pattern) {
+                // this isn't really a comment, it's a `$DIRECTIVE` the
+                // tokenizer needs to see, so leave it alone
+                return 0;
+            }
+
             let leftovers = skip_until(src, matcher);
             return src.len() - leftovers.len();
         }
@@ -277,6 +681,12 @@ fn skip_comments(src: &str) -> usize {
     0
 }
 
+/// Is this the start of a `{$...}` or `(*$...*)` compiler directive, as
+/// opposed to an ordinary comment?
+fn is_compiler_directive(src: &str, opening: &str) -> bool {
+    opening != "//" && src[opening.len()..].starts_with('$')
+}
+
 fn skip_until<'a>(mut src: &'a str, pattern: &str) -> &'a str {
     while !src.is_empty() && !src.starts_with(pattern) {
         let next_char_size = src.chars().next().expect("The string isn't empty").len_utf8();
@@ -302,6 +712,82 @@ comment_test!(comment_skip_curly_braces, "{ baz \n 1234} hello wor\nld" => 13);
 comment_test!(comment_skip_round_brackets, "(* Hello World *) asd" => 17);
 comment_test!(comment_skip_ignores_alphanumeric, "123 hello world" => 0);
 comment_test!(comment_skip_ignores_whitespace, "   (* *) 123 hello world" => 0);
+comment_test!(curly_brace_directives_are_not_skipped, "{$IFDEF DEBUG} 1234" => 0);
+comment_test!(round_bracket_directives_are_not_skipped, "(*$I foo.inc*) 1234" => 0);
+
+//@ Those last two tests highlight the special case mentioned earlier --
+//@ `$DIRECTIVE`s look like comments but are actually meaningful to the
+//@ compiler, so `skip_comments` leaves them for the tokenizer to pick up as a
+//@ proper `TokenKind::CompilerDirective` instead of silently swallowing them.
+
+/// Tokenize a `{$DIRECTIVE args}` or `(*$DIRECTIVE args*)` compiler
+/// directive, splitting the leading directive name (e.g. `IFDEF`, `I`) from
+/// whatever arguments follow it.
+fn tokenize_compiler_directive(data: &str) -> Result<(TokenKind, usize)> {
+    let (name, args, bytes_read) = split_compiler_directive(data)?;
+
+    let tok = TokenKind::CompilerDirective { name: name.to_string(), args: args.to_string() };
+    Ok((tok, bytes_read))
+}
+
+/// Like `tokenize_compiler_directive`, but borrows the directive's name and
+/// arguments out of `data` instead of allocating.
+fn tokenize_compiler_directive_borrowed(data: &str) -> Result<(TokenKindRef, usize)> {
+    let (name, args, bytes_read) = split_compiler_directive(data)?;
+
+    let tok = TokenKindRef::CompilerDirective { name, args };
+    Ok((tok, bytes_read))
+}
+
+/// Split a `{$DIRECTIVE args}` or `(*$DIRECTIVE args*)` compiler directive
+/// into its name, its arguments, and the number of bytes it took up.
+fn split_compiler_directive(data: &str) -> Result<(&str, &str, usize)> {
+    let (body_start, closing) = if data.starts_with("{$") {
+        (2, "}")
+    } else if data.starts_with("(*$") {
+        (3, "*)")
+    } else {
+        bail!("Not the start of a compiler directive");
+    };
+
+    let body = &data[body_start..];
+
+    let end = match body.find(closing) {
+        Some(end) => end,
+        None => bail!(ErrorKind::UnexpectedEOF),
+    };
+
+    let directive = &body[..end];
+    let bytes_read = body_start + end + closing.len();
+
+    let (name, args) = match directive.find(char::is_whitespace) {
+        Some(idx) => (&directive[..idx], directive[idx..].trim()),
+        None => (directive, ""),
+    };
+
+    Ok((name, args, bytes_read))
+}
+
+macro_rules! directive_test {
+    ($name:ident, $src:expr => $name_should_be:expr, $args_should_be:expr) => {
+        #[cfg(test)]
+        #[test]
+        fn $name() {
+            let (got, _bytes_read) = tokenize_compiler_directive($src).unwrap();
+            let should_be = TokenKind::CompilerDirective {
+                name: $name_should_be.to_string(),
+                args: $args_should_be.to_string(),
+            };
+
+            assert_eq!(got, should_be);
+        }
+    }
+}
+
+directive_test!(tokenize_an_ifdef_directive, "{$IFDEF DEBUG}" => "IFDEF", "DEBUG");
+directive_test!(tokenize_an_include_directive, "{$I foo.inc}" => "I", "foo.inc");
+directive_test!(tokenize_a_round_bracket_directive, "(*$IFDEF DEBUG*)" => "IFDEF", "DEBUG");
+directive_test!(tokenize_a_directive_with_no_args, "{$ENDIF}" => "ENDIF", "");
 
 //@ Lastly, we group the whitespace and comment skipping together seeing as they
 //@ both do the job of skipping characters we don't care about.
@@ -347,11 +833,15 @@ pub fn tokenize_single_token(data: &str) -> Result<(TokenKind, usize)> {
         '/' => (TokenKind::Slash, 1),
         '@' => (TokenKind::At, 1),
         '^' => (TokenKind::Carat, 1),
+        '{' => tokenize_compiler_directive(data).chain_err(|| "Couldn't tokenize a compiler directive")?,
+        '(' if data.starts_with("(*$") => tokenize_compiler_directive(data)
+            .chain_err(|| "Couldn't tokenize a compiler directive")?,
         '(' => (TokenKind::OpenParen, 1),
         ')' => (TokenKind::CloseParen, 1),
         '[' => (TokenKind::OpenSquare, 1),
         ']' => (TokenKind::CloseSquare, 1),
-        '0' ... '9' => tokenize_number(data).chain_err(|| "Couldn't tokenize a number")?,
+        '\'' | '#' => tokenize_string(data).chain_err(|| "Couldn't tokenize a string literal")?,
+        '0' ... '9' | '$' => tokenize_number(data).chain_err(|| "Couldn't tokenize a number")?,
         c @ '_' | c if c.is_alphabetic() => tokenize_ident(data)
             .chain_err(|| "Couldn't tokenize an identifier")?,
         other => bail!(ErrorKind::UnknownCharacter(other)),
@@ -360,6 +850,46 @@ pub fn tokenize_single_token(data: &str) -> Result<(TokenKind, usize)> {
     Ok((tok, length))
 }
 
+/// Like `tokenize_single_token`, but produces a [`TokenKindRef`] which
+/// borrows identifiers and strings out of `data` instead of allocating.
+pub fn tokenize_single_token_borrowed(data: &str) -> Result<(TokenKindRef, usize)> {
+    let next = match data.chars().next() {
+        Some(c) => c,
+        None => bail!(ErrorKind::UnexpectedEOF),
+    };
+
+    let (tok, length) = match next {
+        '.' => (TokenKindRef::Dot, 1),
+        '=' => (TokenKindRef::Equals, 1),
+        '+' => (TokenKindRef::Plus, 1),
+        '-' => (TokenKindRef::Minus, 1),
+        '*' => (TokenKindRef::Asterisk, 1),
+        '/' => (TokenKindRef::Slash, 1),
+        '@' => (TokenKindRef::At, 1),
+        '^' => (TokenKindRef::Carat, 1),
+        '{' => tokenize_compiler_directive_borrowed(data).chain_err(|| "Couldn't tokenize a compiler directive")?,
+        '(' if data.starts_with("(*$") => tokenize_compiler_directive_borrowed(data)
+            .chain_err(|| "Couldn't tokenize a compiler directive")?,
+        '(' => (TokenKindRef::OpenParen, 1),
+        ')' => (TokenKindRef::CloseParen, 1),
+        '[' => (TokenKindRef::OpenSquare, 1),
+        ']' => (TokenKindRef::CloseSquare, 1),
+        '\'' | '#' => tokenize_string_borrowed(data).chain_err(|| "Couldn't tokenize a string literal")?,
+        '0' ... '9' | '$' => {
+            let (tok, length) = tokenize_number(data).chain_err(|| "Couldn't tokenize a number")?;
+            match tok {
+                TokenKind::Number(n) => (TokenKindRef::Number(n), length),
+                _ => unreachable!("tokenize_number only ever returns TokenKind::Number"),
+            }
+        },
+        c @ '_' | c if c.is_alphabetic() => tokenize_ident_borrowed(data)
+            .chain_err(|| "Couldn't tokenize an identifier")?,
+        other => bail!(ErrorKind::UnknownCharacter(other)),
+    };
+
+    Ok((tok, length))
+}
+
 //@ Now lets test it, in theory we should get identical results to the other tests
 //@ written up til now.
 
@@ -379,6 +909,34 @@ lexer_test!(central_tokenizer_close_paren, tokenize_single_token, ")" => TokenKi
 lexer_test!(central_tokenizer_open_square, tokenize_single_token, "[" => TokenKind::OpenSquare);
 lexer_test!(central_tokenizer_close_square, tokenize_single_token, "]" => TokenKind::CloseSquare);
 
+#[cfg(test)]
+#[test]
+fn central_tokenizer_string() {
+    let (got, _bytes_read) = tokenize_single_token("'hello'").unwrap();
+    assert_eq!(got, TokenKind::QuotedString("hello".to_string()));
+}
+
+#[cfg(test)]
+#[test]
+fn central_tokenizer_compiler_directive() {
+    let (got, _bytes_read) = tokenize_single_token("{$IFDEF DEBUG}").unwrap();
+    assert_eq!(got, TokenKind::CompilerDirective {
+        name: "IFDEF".to_string(),
+        args: "DEBUG".to_string(),
+    });
+}
+
+#[cfg(test)]
+#[test]
+fn central_tokenizer_hex_number() {
+    let (got, _bytes_read) = tokenize_single_token("$FF").unwrap();
+    assert_eq!(got, TokenKind::Number(NumberLiteral {
+        value: Number::Integer(255),
+        raw: "$FF".to_string(),
+        kind: Some(NumberKind::Hex),
+    }));
+}
+
 
 //@ ## Tying It All Together
 //@
@@ -387,15 +945,57 @@ lexer_test!(central_tokenizer_close_square, tokenize_single_token, "]" => TokenK
 //@ type while still exposing a high-level `tokenize()` function to users.
 
 
-struct Tokenizer<'a> {
+//@ Byte offsets are great for slicing into `remaining_text`, but a human
+//@ reading an error message wants to hear "line 4, column 7", not "byte 63".
+//@ The lexer doesn't have a `CodeMap` to ask -- it's still chewing through a
+//@ bare `&str` -- so `Location` is a much simpler, self-contained cousin of
+//@ `codemap::LineColumn` that the `Tokenizer` updates a character at a time
+//@ as it chomps through the input.
+
+/// A `(line, column)` position in the lexer's input, both zero-based and
+/// counted in characters rather than bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    /// The (zero-based) line number.
+    pub line: u32,
+    /// The (zero-based) column, counted in characters rather than bytes.
+    pub column: u32,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
+
+/// Advance `loc` past `consumed`, incrementing the line and resetting the
+/// column whenever we pass a `\n`.
+fn advance_location(loc: &mut Location, consumed: &str) {
+    for ch in consumed.chars() {
+        if ch == '\n' {
+            loc.line += 1;
+            loc.column = 0;
+        } else {
+            loc.column += 1;
+        }
+    }
+}
+
+/// A lazy tokenizer which can be driven by hand (via `next_token` and
+/// `next_token_resilient`) or as an `Iterator`, so callers who only need the
+/// first few tokens of a large file don't pay for tokenizing the rest of it.
+pub struct Tokenizer<'a> {
     current_index: usize,
+    current_location: Location,
     remaining_text: &'a str,
 }
 
 impl<'a> Tokenizer<'a> {
-    fn new(src: &str) -> Tokenizer {
+    /// Create a new `Tokenizer` which will lex `src` one token at a time.
+    pub fn new(src: &'a str) -> Tokenizer<'a> {
         Tokenizer {
             current_index: 0,
+            current_location: Location::default(),
             remaining_text: src,
         }
     }
@@ -407,14 +1007,59 @@ impl<'a> Tokenizer<'a> {
             Ok(None)
         } else {
             let start = self.current_index;
+            let location = self.current_location;
             let tok = self._next_token()
-                .chain_err(|| ErrorKind::MessageWithLocation(self.current_index,
+                .chain_err(|| ErrorKind::MessageWithLocation(location,
                     "Couldn't read the next token"))?;
             let end = self.current_index;
             Ok(Some((tok, start, end)))
         }
     }
 
+    /// Like `next_token`, but reports the token's start and end as
+    /// `Location`s (line and column) instead of byte offsets.
+    fn next_token_with_locations(&mut self) -> Result<Option<(TokenKind, Location, Location)>> {
+        self.skip_whitespace();
+
+        if self.remaining_text.is_empty() {
+            return Ok(None);
+        }
+
+        let start = self.current_location;
+        let tok = self._next_token()
+            .chain_err(|| ErrorKind::MessageWithLocation(start, "Couldn't read the next token"))?;
+        let end = self.current_location;
+
+        Ok(Some((tok, start, end)))
+    }
+
+    //@ A strict caller wants `next_token` to stop at the first sign of trouble,
+    //@ but a static analyser would rather see as much of a broken file as
+    //@ possible. `next_token_resilient` does exactly the same thing, except
+    //@ that on failure it wraps the offending character up as a
+    //@ `TokenKind::Unknown` and skips over just that one character, so the
+    //@ next call is guaranteed to make progress.
+
+    fn next_token_resilient(&mut self) -> Option<(TokenKind, usize, usize)> {
+        self.skip_whitespace();
+
+        if self.remaining_text.is_empty() {
+            return None;
+        }
+
+        let start = self.current_index;
+
+        match self._next_token() {
+            Ok(tok) => Some((tok, start, self.current_index)),
+            Err(_) => {
+                let bad_char = self.remaining_text.chars().next()
+                    .expect("we already checked remaining_text isn't empty");
+                self.chomp(bad_char.len_utf8());
+                Some((TokenKind::Unknown(bad_char), start, self.current_index))
+            }
+        }
+    }
+
     fn skip_whitespace(&mut self) {
         let skipped = skip(self.remaining_text);
         self.chomp(skipped);
@@ -428,25 +1073,65 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn chomp(&mut self, num_bytes: usize) {
+        advance_location(&mut self.current_location, &self.remaining_text[..num_bytes]);
         self.remaining_text = &self.remaining_text[num_bytes..];
         self.current_index += num_bytes;
     }
 }
 
-/// Turn a string of valid Delphi code into a list of tokens, including the 
+//@ Driving a `Tokenizer` by hand is fine, but it's also just a lazy sequence
+//@ of tokens -- exactly what `Iterator` is for. Implementing it means
+//@ callers can `for token in Tokenizer::new(src) { ... }` and stop early
+//@ without ever building the whole `Vec`, which matters once you're
+//@ tokenizing a source file too big to comfortably hold in memory twice
+//@ over.
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<(TokenKind, usize, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(Some(tok)) => Some(Ok(tok)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn tokenizer_iterator_yields_the_same_tokens_as_tokenize() {
+    let src = "foo = 1 + 2.34";
+
+    let from_iter: Result<Vec<_>> = Tokenizer::new(src).collect();
+    let from_tokenize = tokenize(src);
+
+    assert_eq!(from_iter.unwrap(), from_tokenize.unwrap());
+}
+
+#[cfg(test)]
+#[test]
+fn tokenizer_iterator_stops_early_without_reading_the_rest() {
+    let src = "foo bar `%^&\\";
+
+    let first_two: Vec<_> = Tokenizer::new(src)
+        .take(2)
+        .collect::<Result<_>>()
+        .unwrap();
+
+    assert_eq!(first_two, vec![
+        (TokenKind::from("foo"), 0, 3),
+        (TokenKind::from("bar"), 4, 7),
+    ]);
+}
+
+/// Turn a string of valid Delphi code into a list of tokens, including the
 /// location of that token's start and end point in the original source code.
 ///
-/// Note the token indices represent the half-open interval `[start, end)`, 
+/// Note the token indices represent the half-open interval `[start, end)`,
 /// equivalent to `start .. end` in Rust.
 pub fn tokenize(src: &str) -> Result<Vec<(TokenKind, usize, usize)>> {
-    let mut tokenizer = Tokenizer::new(src);
-    let mut tokens = Vec::new();
-
-    while let Some(tok) = tokenizer.next_token()? {
-        tokens.push(tok);
-    }
-
-    Ok(tokens)
+    Tokenizer::new(src).collect()
 }
 
 //@ Because we also want to make sure the location of tokens are correct, testing 
@@ -470,19 +1155,196 @@ fn tokenize_a_basic_expression() {
     assert_eq!(got, should_be);
 }
 
+#[cfg(test)]
+#[test]
+fn tokenize_an_expression_with_a_hex_literal() {
+    let src = "colour = $00FF00";
+    let should_be = vec![
+        (TokenKind::from("colour"), 0, 6),
+        (TokenKind::Equals, 7, 8),
+        (TokenKind::Number(NumberLiteral {
+            value: Number::Integer(0x00FF00),
+            raw: "$00FF00".to_string(),
+            kind: Some(NumberKind::Hex),
+        }), 9, 16),
+    ];
+
+    let got = tokenize(src).unwrap();
+    assert_eq!(got, should_be);
+}
+
 #[cfg(test)]
 #[test]
 fn tokenizer_detects_invalid_stuff() {
     let src = "foo bar `%^&\\";
-    let index_of_backtick = 8;
+    let location_of_backtick = Location { line: 0, column: 8 };
 
     let err = tokenize(src).unwrap_err();
     match err.kind() {
-        &ErrorKind::MessageWithLocation(loc, _) => assert_eq!(loc, index_of_backtick),
+        &ErrorKind::MessageWithLocation(loc, _) => assert_eq!(loc, location_of_backtick),
         other => panic!("Unexpected error: {}", other),
     }
 }
 
+//@ `tokenize()` is great for a compiler, which can reasonably refuse to carry
+//@ on once it hits broken input. A static analyser doesn't have that luxury --
+//@ if someone's code doesn't compile we still want to tell them as much as we
+//@ can about it, so we need a version of `tokenize()` which can't fail.
+
+/// Like `tokenize()`, but instead of aborting on the first bad character it
+/// wraps it up as a `TokenKind::Unknown` and keeps going, guaranteeing the
+/// returned tokens always cover the whole of `src`.
+pub fn tokenize_resilient(src: &str) -> Vec<(TokenKind, usize, usize)> {
+    let mut tokenizer = Tokenizer::new(src);
+    let mut tokens = Vec::new();
+
+    while let Some(tok) = tokenizer.next_token_resilient() {
+        tokens.push(tok);
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+#[test]
+fn tokenize_resilient_keeps_going_past_invalid_characters() {
+    let src = "foo bar `%&\\";
+
+    let got = tokenize_resilient(src);
+
+    let errors: Vec<_> = got.iter()
+        .filter(|&&(ref kind, _, _)| match *kind {
+            TokenKind::Unknown(_) => true,
+            _ => false,
+        })
+        .collect();
+
+    assert_eq!(errors.len(), 4);
+    assert_eq!(got[0], (TokenKind::from("foo"), 0, 3));
+    assert_eq!(got[1], (TokenKind::from("bar"), 4, 7));
+    assert_eq!(got[2], (TokenKind::Unknown('`'), 8, 9));
+    assert_eq!(got[3], (TokenKind::Unknown('%'), 9, 10));
+    assert_eq!(got[4], (TokenKind::Unknown('&'), 10, 11));
+    assert_eq!(got[5], (TokenKind::Unknown('\\'), 11, 12));
+}
+
+//@ Static analysis tools tend to chew through a lot of source, and a fresh
+//@ `String` for every identifier adds up once files get large. `tokenize()`
+//@ can't avoid that -- its `TokenKind` owns everything -- but a caller who's
+//@ happy to hold onto the original `src` for as long as the tokens live can
+//@ use `tokenize_borrowed()` instead and skip almost all of those
+//@ allocations.
+
+/// Like `tokenize()`, but borrows identifiers and (where possible) quoted
+/// strings directly out of `src` instead of allocating a `String` for each
+/// one.
+pub fn tokenize_borrowed<'a>(src: &'a str) -> Result<Vec<(TokenKindRef<'a>, usize, usize)>> {
+    let mut current_index = 0;
+    let mut current_location = Location::default();
+    let mut remaining = src;
+    let mut tokens = Vec::new();
+
+    loop {
+        let skipped = skip(remaining);
+        advance_location(&mut current_location, &remaining[..skipped]);
+        remaining = &remaining[skipped..];
+        current_index += skipped;
+
+        if remaining.is_empty() {
+            return Ok(tokens);
+        }
+
+        let start = current_index;
+        let location = current_location;
+        let (tok, bytes_read) = tokenize_single_token_borrowed(remaining)
+            .chain_err(|| ErrorKind::MessageWithLocation(location, "Couldn't read the next token"))?;
+
+        advance_location(&mut current_location, &remaining[..bytes_read]);
+        remaining = &remaining[bytes_read..];
+        current_index += bytes_read;
+        tokens.push((tok, start, current_index));
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn tokenize_borrowed_aliases_the_input_buffer() {
+    let src = "foo = 'bar'".to_string();
+
+    let got = tokenize_borrowed(&src).unwrap();
+
+    match got[0].0 {
+        TokenKindRef::Identifier(s) => {
+            assert_eq!(s, "foo");
+            // the identifier should be a slice of `src`, not a fresh allocation
+            assert_eq!(s.as_ptr(), src.as_ptr());
+        }
+        ref other => panic!("Expected an identifier, got {:?}", other),
+    }
+
+    match got[2].0 {
+        TokenKindRef::QuotedString(Cow::Borrowed(s)) => assert_eq!(s, "bar"),
+        ref other => panic!("Expected a borrowed string, got {:?}", other),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn tokenize_borrowed_falls_back_to_owned_for_strings_with_escapes() {
+    let src = "'it''s'";
+
+    let got = tokenize_borrowed(src).unwrap();
+
+    assert_eq!(got.len(), 1);
+    match got[0].0 {
+        TokenKindRef::QuotedString(Cow::Owned(ref s)) => assert_eq!(s, "it's"),
+        ref other => panic!("Expected an owned string, got {:?}", other),
+    }
+}
+
+//@ Byte offsets are what the `CodeMap` wants, but a caller who just wants to
+//@ print a quick error message (or who doesn't have a `CodeMap` handy) would
+//@ rather have the line and column directly. `tokenize_with_locations` is the
+//@ same as `tokenize`, except each token's start and end are `Location`s.
+
+/// Like `tokenize()`, but reports each token's start and end as `Location`s
+/// (line and column) instead of byte offsets.
+pub fn tokenize_with_locations(src: &str) -> Result<Vec<(TokenKind, Location, Location)>> {
+    let mut tokenizer = Tokenizer::new(src);
+    let mut tokens = Vec::new();
+
+    while let Some(tok) = tokenizer.next_token_with_locations()? {
+        tokens.push(tok);
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+#[test]
+fn tokenize_with_locations_tracks_the_line_after_a_newline() {
+    let src = "foo\nbar";
+
+    let got = tokenize_with_locations(src).unwrap();
+
+    assert_eq!(got, vec![
+        (TokenKind::from("foo"), Location { line: 0, column: 0 }, Location { line: 0, column: 3 }),
+        (TokenKind::from("bar"), Location { line: 1, column: 0 }, Location { line: 1, column: 3 }),
+    ]);
+}
+
+#[cfg(test)]
+#[test]
+fn tokenize_with_locations_tracks_the_line_past_a_multi_line_comment() {
+    let src = "{ a\nmulti\nline\ncomment } bar";
+
+    let got = tokenize_with_locations(src).unwrap();
+
+    assert_eq!(got, vec![
+        (TokenKind::from("bar"), Location { line: 3, column: 10 }, Location { line: 3, column: 13 }),
+    ]);
+}
+
 //@ You'll probably notice that we're returning a `TokenKind` and a pair of integers
 //@ inside a tuple, which isn't overly idiomatic. Idiomatic Rust would bundle 
 //@ these up into a more strongly typed tuple of `TokenKind` and `Span`, where a span
@@ -114,6 +114,19 @@ pub mod errors;
 
 pub mod codemap;
 
+//@ Knowing *which* bytes of a file a span covers isn't much use to a human
+//@ reader on its own, so we also have a `diagnostics` module which turns a
+//@ `Span` (plus a message) into a `rustc`-style report, complete with a
+//@ snippet of the offending source and a caret pointing at the problem.
+
+pub mod diagnostics;
+
+//@ A common ingredient in a good diagnostic is a "did you mean ...?"
+//@ suggestion, so when resolution reports an unknown identifier it can point
+//@ the user at the closest name it actually knows about.
+
+pub mod suggestions;
+
 //@ Finally, there's the `Driver`. He's in charge of the show an is usually the
 //@ thing you'll want to invoke or hook into to tweak the analysis process.
 
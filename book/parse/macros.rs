@@ -39,7 +39,7 @@ macro_rules! tok {
 #[cfg(test)]
 mod tests {
     use codemap::Span;
-    use lex::{Token, TokenKind};
+    use lex::{Token, TokenKind, Number, NumberLiteral};
 
     macro_rules! token_macro_test {
         ($name:ident, $from:tt => $to:expr) => {
@@ -55,8 +55,10 @@ mod tests {
 
     token_macro_test!(tok_expands_to_dot, Dot => TokenKind::Dot);
     token_macro_test!(tok_expands_to_openparen, OpenParen => TokenKind::OpenParen);
-    token_macro_test!(tok_expands_to_integer, 1234 => TokenKind::Integer(1234));
-    token_macro_test!(tok_expands_to_decimal, 12.34 => TokenKind::Decimal(12.34));
+    token_macro_test!(tok_expands_to_integer, 1234 =>
+        TokenKind::Number(NumberLiteral { value: Number::Integer(1234), raw: "1234".to_string(), kind: None }));
+    token_macro_test!(tok_expands_to_decimal, 12.34 =>
+        TokenKind::Number(NumberLiteral { value: Number::Decimal(12.34), raw: "12.34".to_string(), kind: None }));
     token_macro_test!(tok_expands_to_identifier, "Hello" => TokenKind::Identifier("Hello".to_string()));
 }
 
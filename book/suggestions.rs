@@ -0,0 +1,170 @@
+//@ # Suggestions
+//@
+//@ Once we start resolving names (e.g. looking up a variable or a function
+//@ call) we're inevitably going to run into typos. Rather than just saying
+//@ "unknown identifier `ShowMesage`" it's much friendlier to also suggest
+//@ `did you mean \`ShowMessage\`?`, which is exactly what this module is for.
+
+//! Suggest a likely fix for a typo'd identifier.
+
+use std::cmp;
+use std::mem;
+
+//@ The edit distance between two strings is calculated using the restricted
+//@ Damerau-Levenshtein algorithm (i.e. normal Levenshtein plus adjacent
+//@ transpositions, like `teh` -> `the`). To avoid wasting time comparing a
+//@ target against a candidate that's nowhere close, we also take a `max_dist`
+//@ cap and bail out early the moment every entry in a row exceeds it.
+
+/// Find the `candidate` which is the closest match for `target`, as long as
+/// it's within both `max_dist` edits and `default_max_distance()` of that
+/// candidate. Comparisons are case-insensitive, but the original (un-lowered)
+/// candidate is returned.
+pub fn find_best_match<'a>(candidates: &'a [String], target: &str, max_dist: usize) -> Option<&'a str> {
+    candidates.iter()
+        .filter_map(|candidate| {
+            // never suggest something further away than `max_dist`, but also
+            // tighten the cap per-candidate so a short candidate can't be
+            // "matched" purely because `max_dist` was sized for longer ones
+            let cap = cmp::min(max_dist, default_max_distance(target, candidate));
+            let dist = restricted_edit_distance(candidate, target, cap)?;
+            Some((candidate.as_str(), dist))
+        })
+        .min_by(|&(a, a_dist), &(b, b_dist)| a_dist.cmp(&b_dist).then_with(|| a.cmp(b)))
+        .map(|(candidate, _)| candidate)
+}
+
+//@ A sensible default is to only suggest a candidate if it's within a third
+//@ of the longer string's length. Much further than that and the suggestion
+//@ tends to just be noise.
+
+/// A sensible default `max_dist` to pass to `find_best_match()`, based on the
+/// length of the strings being compared.
+pub fn default_max_distance(target: &str, candidate: &str) -> usize {
+    cmp::max(target.len(), candidate.len()) / 3
+}
+
+/// Calculate the restricted Damerau-Levenshtein distance between `candidate`
+/// and `target`, giving up early (returning `None`) if it's ever guaranteed
+/// to be more than `max_dist`.
+fn restricted_edit_distance(candidate: &str, target: &str, max_dist: usize) -> Option<usize> {
+    let target: Vec<char> = target.chars().flat_map(char::to_lowercase).collect();
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+
+    let target_len = target.len();
+    let candidate_len = candidate.len();
+
+    // two rows ago (needed for the transposition case), the previous row, and
+    // the row we're currently filling in
+    let mut two_rows_back: Vec<usize> = vec![0; target_len + 1];
+    let mut prev_row: Vec<usize> = (0..=target_len).collect();
+    let mut current_row: Vec<usize> = vec![0; target_len + 1];
+
+    for i in 1..=candidate_len {
+        current_row[0] = i;
+        let mut row_min = current_row[0];
+
+        for j in 1..=target_len {
+            let cost = if candidate[i - 1] == target[j - 1] { 0 } else { 1 };
+
+            let mut value = cmp::min(
+                prev_row[j] + 1, // deletion
+                cmp::min(
+                    current_row[j - 1] + 1, // insertion
+                    prev_row[j - 1] + cost, // substitution
+                ),
+            );
+
+            if i > 1 && j > 1 && candidate[i - 1] == target[j - 2] && candidate[i - 2] == target[j - 1] {
+                // swapping the last two characters turns one string into the other
+                value = cmp::min(value, two_rows_back[j - 2] + cost);
+            }
+
+            current_row[j] = value;
+            row_min = cmp::min(row_min, value);
+        }
+
+        if row_min > max_dist {
+            return None;
+        }
+
+        mem::swap(&mut two_rows_back, &mut prev_row);
+        mem::swap(&mut prev_row, &mut current_row);
+    }
+
+    let distance = prev_row[target_len];
+
+    if distance <= max_dist {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_strings_have_zero_distance() {
+        assert_eq!(restricted_edit_distance("ShowMessage", "ShowMessage", 10), Some(0));
+    }
+
+    #[test]
+    fn distance_is_case_insensitive() {
+        assert_eq!(restricted_edit_distance("ShowMessage", "showmessage", 10), Some(0));
+    }
+
+    #[test]
+    fn a_single_substitution() {
+        assert_eq!(restricted_edit_distance("cat", "cot", 10), Some(1));
+    }
+
+    #[test]
+    fn an_adjacent_transposition_only_costs_one() {
+        assert_eq!(restricted_edit_distance("teh", "the", 10), Some(1));
+    }
+
+    #[test]
+    fn distances_further_than_the_cap_are_rejected() {
+        assert_eq!(restricted_edit_distance("foo", "completely_different", 3), None);
+    }
+
+    #[test]
+    fn find_best_match_picks_the_closest_candidate() {
+        let candidates = vec![
+            "ShowMessage".to_string(),
+            "ShowDialog".to_string(),
+            "Unrelated".to_string(),
+        ];
+
+        let got = find_best_match(&candidates, "ShowMesage", 3);
+        assert_eq!(got, Some("ShowMessage"));
+    }
+
+    #[test]
+    fn find_best_match_returns_none_when_nothing_is_close_enough() {
+        let candidates = vec!["ShowMessage".to_string()];
+
+        let got = find_best_match(&candidates, "CompletelyUnrelated", 3);
+        assert_eq!(got, None);
+    }
+
+    #[test]
+    fn default_max_distance_is_a_third_of_the_longer_string() {
+        assert_eq!(default_max_distance("ShowMessage", "ShowMesage"), 3);
+        assert_eq!(default_max_distance("foo", "completely_different"), 6);
+    }
+
+    #[test]
+    fn find_best_match_rejects_a_short_candidate_even_with_a_generous_max_dist() {
+        let candidates = vec!["ab".to_string()];
+
+        // "ab" is only 6 edits from "abcdefgh", well within the generous
+        // `max_dist` below, but `default_max_distance` caps the comparison at
+        // a third of the longer string's length (8 / 3 = 2) -- it should
+        // never be suggested as a match for a target this much longer.
+        let got = find_best_match(&candidates, "abcdefgh", 10);
+        assert_eq!(got, None);
+    }
+}